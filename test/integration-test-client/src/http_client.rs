@@ -14,12 +14,36 @@ pub struct ConnAddr {
 pub struct GetRequest {
     pub path: String,
     pub client_header: ClientHeader,
+    /// An inclusive byte range to request. `AutoGenerated` headers translate this into a
+    /// `Range: bytes=start-end` request header; left `None` for a regular full-body `GET`.
+    pub range: Option<ByteRange>,
+}
+
+#[derive(Debug, Hash, Clone, Copy, PartialEq, Eq)]
+pub struct ByteRange {
+    pub start: u64,
+    pub end: u64,
 }
 
 pub struct GetRequestTest {
     pub conn_addr: ConnAddr,
     pub get_requests: Vec<GetRequest>,
+    /// How long to wait for the whole batch of `get_requests`, measured in windows of this
+    /// length. Left unset, it's derived from `first_byte_timeout`/`read_timeout` so it can't cut
+    /// the batch off before those timeouts (and their retry) get a chance to complete. This is
+    /// not a hard ceiling on total transfer time: if any piece of a response body was read
+    /// during a window, another window is granted, so a large but steadily progressing download
+    /// isn't dropped just because it takes longer than `timeout` overall. The wait ends after
+    /// the first window in which no body data was read; header bytes do not count as progress.
     pub timeout: Option<Duration>,
+    /// How long to wait for the first byte of a response's header. This is kept separate
+    /// from `read_timeout` because a caching proxy may legitimately block for a long time
+    /// fetching a cold object from an upstream mirror before any bytes flow.
+    pub first_byte_timeout: Option<Duration>,
+    /// How long to wait for each read once a response has started arriving.
+    pub read_timeout: Option<Duration>,
+    /// Upper bound on the size of a single response header block, in bytes.
+    pub max_header_size: Option<usize>,
 }
 
 pub enum ClientHeader {
@@ -29,6 +53,10 @@ pub enum ClientHeader {
 
 const BUF_SIZE: usize = 4096;
 
+const DEFAULT_FIRST_BYTE_TIMEOUT: Duration = Duration::from_secs(30);
+const DEFAULT_READ_TIMEOUT: Duration = Duration::from_secs(2);
+const DEFAULT_MAX_HEADER_SIZE: usize = 8192;
+
 const HEADER_SEPARATOR: &[u8; 4] = b"\r\n\r\n";
 pub const HEADER_SEPARATOR_STR: &str = "\r\n\r\n";
 
@@ -37,6 +65,31 @@ pub struct HeaderResult {
     pub status_code: u32,
     pub content_length: usize,
     pub cached: bool,
+    pub chunked: bool,
+    /// Whether the server intends to keep this connection open for a subsequent request, per
+    /// the `Connection` header and HTTP version (and `101 Switching Protocols`/`Connection:
+    /// upgrade`, which never allow reuse as a plain HTTP connection).
+    pub keep_alive: bool,
+    /// All response headers in wire order, as `(name, value)` pairs. Header names should be
+    /// looked up case-insensitively via `header_value`, since upstreams vary casing.
+    pub headers: Vec<(String, String)>,
+    /// The parsed `Content-Range` header of a `206 Partial Content` response, if any.
+    pub content_range: Option<ContentRange>,
+}
+
+/// A parsed `Content-Range: bytes start-end/total` response header. `total` is `None` when the
+/// server reports it as `*` (unknown).
+#[derive(Debug, Hash, Clone, Copy, PartialEq, Eq)]
+pub struct ContentRange {
+    pub start: u64,
+    pub end: u64,
+    pub total: Option<u64>,
+}
+
+/// Looks up a header value by name, case-insensitively, returning the first match in wire
+/// order.
+pub fn header_value<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers.iter().find(|(k, _)| k.eq_ignore_ascii_case(name)).map(|(_, v)| v.as_str())
 }
 
 #[derive(Debug, Hash, Clone, PartialEq, Eq)]
@@ -49,9 +102,39 @@ pub struct BodyResult {
 pub struct HttpGetResult {
     pub header_result: HeaderResult,
     pub payload_result: Option<BodyResult>,
+    /// Whether this request was sent over a connection already used by an earlier
+    /// `GetRequest` in the same batch. `false` for the first request, and for any request
+    /// that had to reconnect because the previous response refused to keep the connection
+    /// alive.
+    pub connection_reused: bool,
+}
+
+/// Which phase of the request/response cycle a timeout was observed in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeoutPhase {
+    FirstByte,
+    Read,
+}
+
+/// Everything that can go wrong while performing a `GET` and reading its response, short of
+/// the overall `GetRequestTest::timeout` expiring (which is reported as `None`, not an error).
+#[derive(Debug)]
+pub enum HttpError {
+    ConnectionFailed(std::io::Error),
+    HeaderTooLarge,
+    MalformedStatusLine,
+    MalformedHeaderLine,
+    MalformedContentRange,
+    MissingContentLength,
+    InvalidContentLength(std::num::ParseIntError),
+    MalformedChunkEncoding,
+    UnexpectedEof,
+    BodySizeMismatch { expected: usize, actual: usize },
+    Timeout(TimeoutPhase),
+    Io(std::io::Error),
 }
 
-pub fn http_get(request: GetRequestTest, testcase: &'static str) -> Option<Vec<HttpGetResult>> {
+pub fn http_get(request: GetRequestTest, testcase: &'static str) -> Option<Result<Vec<HttpGetResult>, HttpError>> {
     http_get_with_header_chunked(request, None, testcase)
 }
 
@@ -59,60 +142,129 @@ pub fn http_get_with_header_chunked(
     request_test: GetRequestTest,
     maybe_pattern: Option<ChunkPattern>,
     testcase: &'static str,
-) -> Option<Vec<HttpGetResult>> {
+) -> Option<Result<Vec<HttpGetResult>, HttpError>> {
     let host = request_test.conn_addr.host.clone();
-    let (sender, receiver) = mpsc::channel::<Vec<HttpGetResult>>();
-    let timeout = request_test.timeout.unwrap_or(Duration::from_millis(5000));
+    let (sender, receiver) = mpsc::channel::<Result<Vec<HttpGetResult>, HttpError>>();
+    let first_byte_timeout = request_test.first_byte_timeout.unwrap_or(DEFAULT_FIRST_BYTE_TIMEOUT);
+    let read_timeout = request_test.read_timeout.unwrap_or(DEFAULT_READ_TIMEOUT);
+    let max_header_size = request_test.max_header_size.unwrap_or(DEFAULT_MAX_HEADER_SIZE);
+    // If the caller doesn't set an overall timeout explicitly, derive one from the per-request
+    // timeouts rather than falling back to a small fixed value that would cut off a slow first
+    // byte (and its retry) before they ever get a chance to complete. Each request in the batch
+    // is handled sequentially, so the worst case scales with how many there are.
+    let request_count = request_test.get_requests.len().max(1) as u32;
+    let default_timeout = (first_byte_timeout + read_timeout) * 2 * request_count;
+    let timeout = request_test.timeout.unwrap_or(default_timeout);
+    // Lets the worker thread notify the waiting loop below every time it reads another piece
+    // of a response body, so a slow-but-moving transfer can push the deadline back instead of
+    // being measured against the time the whole batch has been running.
+    let (progress_sender, progress_receiver) = mpsc::channel::<()>();
     thread::spawn(move || {
         let conn_addr = request_test.conn_addr.clone();
         info!("{}: Connecting to {:?}", testcase, conn_addr);
-        let mut stream = TcpStream::connect((conn_addr.host, conn_addr.port)).unwrap();
-        info!("{}: Connection established.", testcase);
-        let results = request_test.get_requests.iter().map(|request| {
-            let header: String = match &request.client_header {
-                ClientHeader::AutoGenerated =>
-                    format!("GET {} HTTP/1.1\r\nHost: {}{}", request.path, request_test.conn_addr.host, HEADER_SEPARATOR_STR),
-                ClientHeader::Custom(h) => h.clone(),
-            };
-            let pattern = maybe_pattern.unwrap_or_else(|| ChunkPattern {
-                chunk_size: header.len(),
-                wait_interval: Duration::from_millis(0),
-            });
-            let header_bytes = header.as_bytes();
-            for header_chunk in header_bytes.chunks(pattern.chunk_size) {
-                stream.write(header_chunk).unwrap();
-            }
-            info!("{}: Sending header.", testcase);
-            let header_result = read_header(&mut stream);
-            info!("{}: Successfully fetched header: {:?}", testcase, header_result);
-            let payload_result = match header_result.content_length {
-                0 => {
-                    info!("{}: Content length has size zero", testcase);
-                    None
-                },
-                content_length => {
-                    info!("{}: Successfully fetched payload, size is {}", testcase, content_length);
-                    Some(body_result(&mut stream, content_length))
-                },
-            };
-            HttpGetResult {
-                header_result,
-                payload_result,
+        let result = (|| -> Result<Vec<HttpGetResult>, HttpError> {
+            let mut stream = TcpStream::connect((conn_addr.host.clone(), conn_addr.port)).map_err(HttpError::ConnectionFailed)?;
+            info!("{}: Connection established.", testcase);
+            // `None` until the first response's header arrives, then tracks whether the most
+            // recently read response allows the connection to be reused for the next request.
+            let mut keep_alive_from_previous: Option<bool> = None;
+            let mut results = Vec::with_capacity(request_test.get_requests.len());
+            for request in request_test.get_requests.iter() {
+                let connection_reused = match keep_alive_from_previous {
+                    None => false,
+                    Some(true) => true,
+                    Some(false) => {
+                        info!("{}: Previous response did not keep the connection alive, reconnecting.", testcase);
+                        stream = TcpStream::connect((conn_addr.host.clone(), conn_addr.port)).map_err(HttpError::ConnectionFailed)?;
+                        false
+                    },
+                };
+                let header: String = match &request.client_header {
+                    ClientHeader::AutoGenerated => {
+                        let range_header = match request.range {
+                            Some(range) => format!("\r\nRange: bytes={}-{}", range.start, range.end),
+                            None => String::new(),
+                        };
+                        format!(
+                            "GET {} HTTP/1.1\r\nHost: {}{}{}",
+                            request.path, request_test.conn_addr.host, range_header, HEADER_SEPARATOR_STR,
+                        )
+                    },
+                    ClientHeader::Custom(h) => h.clone(),
+                };
+                let pattern = maybe_pattern.unwrap_or_else(|| ChunkPattern {
+                    chunk_size: header.len(),
+                    wait_interval: Duration::from_millis(0),
+                });
+                let send_header = |stream: &mut TcpStream| -> Result<(), HttpError> {
+                    for header_chunk in header.as_bytes().chunks(pattern.chunk_size) {
+                        stream.write(header_chunk).map_err(HttpError::Io)?;
+                    }
+                    Ok(())
+                };
+                send_header(&mut stream)?;
+                info!("{}: Sending header.", testcase);
+                let header_result = match read_header(&mut stream, first_byte_timeout, read_timeout, max_header_size) {
+                    Ok(header_result) => header_result,
+                    Err(HttpError::Timeout(TimeoutPhase::FirstByte)) => {
+                        info!("{}: Timed out waiting for first response byte, retrying request once", testcase);
+                        send_header(&mut stream)?;
+                        read_header(&mut stream, first_byte_timeout, read_timeout, max_header_size)?
+                    },
+                    Err(e) => return Err(e),
+                };
+                info!("{}: Successfully fetched header: {:?}", testcase, header_result);
+                let payload_result = if header_result.chunked {
+                    info!("{}: Response is chunked, decoding chunked body", testcase);
+                    Some(body_result_chunked(&mut stream, &progress_sender)?)
+                } else {
+                    match header_result.content_length {
+                        0 => {
+                            info!("{}: Content length has size zero", testcase);
+                            None
+                        },
+                        content_length => {
+                            info!("{}: Successfully fetched payload, size is {}", testcase, content_length);
+                            Some(body_result(&mut stream, content_length, &progress_sender)?)
+                        },
+                    }
+                };
+                keep_alive_from_previous = Some(header_result.keep_alive);
+                results.push(HttpGetResult {
+                    header_result,
+                    payload_result,
+                    connection_reused,
+                });
             }
-        }).collect::<Vec<HttpGetResult>>();
+            Ok(results)
+        })();
         info!("{}: Sending results back to main thread", testcase);
-        sender.send(results)
+        sender.send(result)
     });
     info!("{}: Waiting for response from thread for request at host {}, Timeout is {:?}", testcase, host, timeout);
-    match receiver.recv_timeout(timeout) {
-        Ok(r) => {
-            info!("{}: Got response", testcase);
-            Some(r)
-        },
-        Err(_) => {
-            info!("{}: No response received within {:?}", testcase, timeout);
-            None
-        },
+    loop {
+        match receiver.recv_timeout(timeout) {
+            Ok(r) => {
+                info!("{}: Got response", testcase);
+                break Some(r);
+            },
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                // A body read landed in the meantime, so the batch is still making progress
+                // even though it hasn't finished within `timeout` yet; push the deadline back
+                // instead of giving up on a transfer that's merely large or slow.
+                let progressed = progress_receiver.try_iter().count() > 0;
+                if progressed {
+                    info!("{}: Body transfer still in progress, extending wait", testcase);
+                    continue;
+                }
+                info!("{}: No response received within {:?}", testcase, timeout);
+                break None;
+            },
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                info!("{}: Worker thread ended without sending a result", testcase);
+                break None;
+            },
+        }
     }
 }
 
@@ -122,85 +274,257 @@ pub struct ChunkPattern {
     pub wait_interval: Duration,
 }
 
-fn read_header(stream: &mut TcpStream) -> HeaderResult {
-    let payload = &mut[0; BUF_SIZE];
-    let mut size_read = 0;
+fn is_timeout(e: &std::io::Error) -> bool {
+    matches!(e.kind(), std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut)
+}
+
+fn read_header(
+    stream: &mut TcpStream,
+    first_byte_timeout: Duration,
+    read_timeout: Duration,
+    max_header_size: usize,
+) -> Result<HeaderResult, HttpError> {
+    stream.set_read_timeout(Some(first_byte_timeout)).map_err(HttpError::Io)?;
+    let mut buffer: Vec<u8> = Vec::new();
+    let mut byte = [0; 1];
     loop {
-        match stream.read(&mut payload[size_read..size_read + 1]) {
+        match stream.read(&mut byte) {
             Ok(1) => {
-                size_read += 1;
-                if size_read >= HEADER_SEPARATOR.len() {
-                    if &payload[size_read - HEADER_SEPARATOR.len()..size_read] == HEADER_SEPARATOR {
-                        break;
-                    }
+                if buffer.is_empty() {
+                    stream.set_read_timeout(Some(read_timeout)).map_err(HttpError::Io)?;
+                }
+                buffer.push(byte[0]);
+                if buffer.len() > max_header_size {
+                    return Err(HttpError::HeaderTooLarge);
+                }
+                if buffer.ends_with(HEADER_SEPARATOR) {
+                    break;
                 }
             },
-            Ok(s) => panic!("Unexpected size while reading from socket: {}", s),
-            Err(e) => panic!("Unable to read header: {:?}", e),
+            Ok(0) => return Err(HttpError::UnexpectedEof),
+            Ok(s) => unreachable!("read into a 1-byte slice returned size {}", s),
+            Err(e) if buffer.is_empty() && is_timeout(&e) => return Err(HttpError::Timeout(TimeoutPhase::FirstByte)),
+            Err(e) if is_timeout(&e) => return Err(HttpError::Timeout(TimeoutPhase::Read)),
+            Err(e) => return Err(HttpError::Io(e)),
+        }
+    }
+    let header_bytes = &buffer[..buffer.len() - HEADER_SEPARATOR.len()];
+    let (http_version, status_code, headers) = parse_header_block(header_bytes)?;
+    let cached = cached(&headers);
+    let chunked = transfer_encoding_chunked(&headers);
+    // A chunked response carries no `Content-Length` (RFC 7230 §3.3.2 forbids sending both),
+    // so only require/parse it for the non-chunked case; `body_result_chunked` ignores it.
+    let content_length = if chunked { 0 } else { content_length(&headers)? };
+    let keep_alive = connection_keep_alive(&http_version, status_code, &headers);
+    let content_range = parse_content_range(&headers)?;
+    if status_code == 206 && !chunked {
+        if let Some(range) = content_range {
+            let expected = range.end.checked_sub(range.start)
+                .and_then(|size| size.checked_add(1))
+                .ok_or(HttpError::MalformedContentRange)? as usize;
+            if expected != content_length {
+                return Err(HttpError::BodySizeMismatch { expected, actual: content_length });
+            }
         }
     }
-    let header_bytes = &payload[..size_read - HEADER_SEPARATOR.len()];
-    let content_length = content_length(header_bytes);
-    let status_code = status_code(header_bytes);
-    let cached = cached(header_bytes);
-    HeaderResult {
+    Ok(HeaderResult {
         status_code,
         content_length,
         cached,
+        chunked,
+        keep_alive,
+        headers,
+        content_range,
+    })
+}
+
+// Parses a `Content-Range: bytes start-end/total` response header, where `total` may be `*`
+// for an unknown total size.
+fn parse_content_range(headers: &[(String, String)]) -> Result<Option<ContentRange>, HttpError> {
+    let value = match header_value(headers, "Content-Range") {
+        Some(value) => value.trim(),
+        None => return Ok(None),
+    };
+    let range_and_total = value.strip_prefix("bytes ").ok_or(HttpError::MalformedContentRange)?;
+    let (range, total) = range_and_total.split_once('/').ok_or(HttpError::MalformedContentRange)?;
+    let (start, end) = range.split_once('-').ok_or(HttpError::MalformedContentRange)?;
+    let start = start.parse::<u64>().map_err(|_| HttpError::MalformedContentRange)?;
+    let end = end.parse::<u64>().map_err(|_| HttpError::MalformedContentRange)?;
+    if start > end {
+        return Err(HttpError::MalformedContentRange);
+    }
+    let total = if total == "*" {
+        None
+    } else {
+        Some(total.parse::<u64>().map_err(|_| HttpError::MalformedContentRange)?)
+    };
+    Ok(Some(ContentRange { start, end, total }))
+}
+
+// Parses a status line plus zero or more `Name: value` header lines (already split from the
+// trailing `\r\n\r\n` separator) into the HTTP version, status code, and an ordered list of
+// header pairs.
+fn parse_header_block(header_bytes: &[u8]) -> Result<(String, u32, Vec<(String, String)>), HttpError> {
+    let text = std::str::from_utf8(header_bytes).map_err(|_| HttpError::MalformedStatusLine)?;
+    let mut lines = text.split("\r\n");
+    let status_line = lines.next().ok_or(HttpError::MalformedStatusLine)?;
+    let (http_version, status_code) = parse_status_line(status_line)?;
+    let headers = lines
+        .map(|line| {
+            let (name, value) = line.split_once(": ").ok_or(HttpError::MalformedHeaderLine)?;
+            Ok((name.to_string(), value.to_string()))
+        })
+        .collect::<Result<Vec<(String, String)>, HttpError>>()?;
+    Ok((http_version, status_code, headers))
+}
+
+fn parse_status_line(status_line: &str) -> Result<(String, u32), HttpError> {
+    let mut parts = status_line.split(' ');
+    let http_version = parts.next().ok_or(HttpError::MalformedStatusLine)?.to_string();
+    let status_code = parts
+        .next()
+        .and_then(|code| code.parse::<u32>().ok())
+        .ok_or(HttpError::MalformedStatusLine)?;
+    Ok((http_version, status_code))
+}
+
+// HTTP/1.1 connections stay open unless told `Connection: close`; HTTP/1.0 connections close
+// unless told `Connection: keep-alive`. Either way, a protocol upgrade (`101 Switching
+// Protocols` or `Connection: upgrade`) means the socket is no longer a plain HTTP connection
+// and can't be reused for the next `GetRequest`.
+fn connection_keep_alive(http_version: &str, status_code: u32, headers: &[(String, String)]) -> bool {
+    let connection = header_value(headers, "Connection").map(|value| value.to_ascii_lowercase());
+    if status_code == 101 || connection.as_deref() == Some("upgrade") {
+        return false;
+    }
+    match connection.as_deref() {
+        Some("close") => false,
+        Some("keep-alive") => true,
+        _ => http_version.eq_ignore_ascii_case("HTTP/1.1"),
     }
 }
 
-fn body_result(stream: &mut TcpStream, content_length: usize) -> BodyResult {
+fn body_result(stream: &mut TcpStream, content_length: usize, progress: &mpsc::Sender<()>) -> Result<BodyResult, HttpError> {
     let mut hasher = Sha256::new();
     let payload = &mut[0; BUF_SIZE];
     let mut size_read = 0;
     while size_read < content_length {
-        match stream.read(payload) {
+        let to_read = std::cmp::min(BUF_SIZE, content_length - size_read);
+        match stream.read(&mut payload[..to_read]) {
+            Ok(0) => return Err(HttpError::UnexpectedEof),
             Ok(size) => {
                 size_read += size;
                 hasher.update(&payload[..size]);
+                let _ = progress.send(());
+            }
+            Err(e) if is_timeout(&e) => return Err(HttpError::Timeout(TimeoutPhase::Read)),
+            Err(e) => return Err(HttpError::Io(e)),
+        }
+    }
+    Ok(BodyResult {
+        sha: hasher.finalize().to_vec(),
+        size: size_read,
+    })
+}
+
+// Decodes a `Transfer-Encoding: chunked` body: each chunk is a hex size line (chunk
+// extensions after `;` are ignored), that many payload bytes, and a trailing CRLF. A
+// chunk of size zero ends the body, followed by optional trailer headers up to a blank
+// line. `BodyResult.size` reflects the decoded payload bytes, not the wire bytes.
+fn body_result_chunked(stream: &mut TcpStream, progress: &mpsc::Sender<()>) -> Result<BodyResult, HttpError> {
+    let mut hasher = Sha256::new();
+    let mut size_read = 0;
+    loop {
+        let size_line = read_chunked_line(stream)?;
+        let chunk_size = parse_chunk_size(&size_line)?;
+        if chunk_size == 0 {
+            loop {
+                let trailer_line = read_chunked_line(stream)?;
+                if trailer_line.is_empty() {
+                    break;
+                }
             }
-            Err(e) => panic!("Unable to read body: {:?}", e),
+            break;
         }
+        read_chunk_payload(stream, chunk_size, &mut hasher)?;
+        consume_chunk_terminator(stream)?;
+        size_read += chunk_size;
+        let _ = progress.send(());
     }
-    BodyResult {
+    Ok(BodyResult {
         sha: hasher.finalize().to_vec(),
         size: size_read,
+    })
+}
+
+fn read_chunked_line(stream: &mut TcpStream) -> Result<Vec<u8>, HttpError> {
+    let mut line = Vec::new();
+    let mut byte = [0; 1];
+    loop {
+        match stream.read(&mut byte) {
+            Ok(1) => {
+                line.push(byte[0]);
+                if line.ends_with(b"\r\n") {
+                    line.truncate(line.len() - 2);
+                    return Ok(line);
+                }
+            },
+            Ok(0) => return Err(HttpError::UnexpectedEof),
+            Ok(s) => unreachable!("read into a 1-byte slice returned size {}", s),
+            Err(e) if is_timeout(&e) => return Err(HttpError::Timeout(TimeoutPhase::Read)),
+            Err(e) => return Err(HttpError::Io(e)),
+        }
+    }
+}
+
+fn parse_chunk_size(size_line: &[u8]) -> Result<usize, HttpError> {
+    let size_str = std::str::from_utf8(size_line).map_err(|_| HttpError::MalformedChunkEncoding)?;
+    let size_str = size_str.split(';').next().unwrap().trim();
+    usize::from_str_radix(size_str, 16).map_err(|_| HttpError::MalformedChunkEncoding)
+}
+
+fn read_chunk_payload(stream: &mut TcpStream, chunk_size: usize, hasher: &mut Sha256) -> Result<(), HttpError> {
+    let payload = &mut[0; BUF_SIZE];
+    let mut size_read = 0;
+    while size_read < chunk_size {
+        let to_read = std::cmp::min(BUF_SIZE, chunk_size - size_read);
+        match stream.read(&mut payload[..to_read]) {
+            Ok(0) => return Err(HttpError::UnexpectedEof),
+            Ok(size) => {
+                size_read += size;
+                hasher.update(&payload[..size]);
+            }
+            Err(e) if is_timeout(&e) => return Err(HttpError::Timeout(TimeoutPhase::Read)),
+            Err(e) => return Err(HttpError::Io(e)),
+        }
+    }
+    Ok(())
+}
+
+fn consume_chunk_terminator(stream: &mut TcpStream) -> Result<(), HttpError> {
+    let mut crlf = [0; 2];
+    match stream.read_exact(&mut crlf) {
+        Ok(()) if &crlf == b"\r\n" => Ok(()),
+        Ok(()) => Err(HttpError::MalformedChunkEncoding),
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => Err(HttpError::UnexpectedEof),
+        Err(e) if is_timeout(&e) => Err(HttpError::Timeout(TimeoutPhase::Read)),
+        Err(e) => Err(HttpError::Io(e)),
+    }
+}
+
+fn transfer_encoding_chunked(headers: &[(String, String)]) -> bool {
+    header_value(headers, "Transfer-Encoding")
+        .map(|value| value.eq_ignore_ascii_case("chunked"))
+        .unwrap_or(false)
+}
+
+fn content_length(headers: &[(String, String)]) -> Result<usize, HttpError> {
+    match header_value(headers, "Content-Length") {
+        Some(value) => value.trim().parse::<usize>().map_err(HttpError::InvalidContentLength),
+        None => Err(HttpError::MissingContentLength),
     }
 }
 
-fn content_length(header: &[u8]) -> usize {
-    let content_length = get_header_value(header, b"Content-Length: ");
-    content_length.parse::<usize>().unwrap()
-}
-
-fn cached(header: &[u8]) -> bool {
-    let payload_origin = get_header_value(header, b"Flexo-Payload-Origin: ");
-    payload_origin == "Cache"
-}
-
-fn status_code(header: &[u8]) -> u32 {
-    let keyword = b" ";
-    let start_idx = header
-        .iter()
-        .position(|header_part| header_part == &b' ')
-        .unwrap() + keyword.len();
-    let end_idx = header[start_idx..]
-        .iter()
-        .position(|header_part| header_part == &b' ')
-        .unwrap() + start_idx;
-    let status_code = &header[start_idx..end_idx];
-    std::string::String::from_utf8(Vec::from(status_code)).unwrap().parse::<u32>().unwrap()
-}
-
-fn get_header_value(header: &[u8], keyword: &[u8]) -> String {
-    let start_idx = header
-        .windows(keyword.len())
-        .position(|header_part| header_part == keyword).unwrap() + keyword.len();
-    let end_idx = header[start_idx..]
-        .iter()
-        .position(|header_part| header_part == &b'\r').map(|i| i + start_idx)
-        .unwrap_or(header.len());
-    let content_length = &header[start_idx..end_idx];
-    std::string::String::from_utf8(Vec::from(content_length)).unwrap()
+fn cached(headers: &[(String, String)]) -> bool {
+    header_value(headers, "Flexo-Payload-Origin") == Some("Cache")
 }